@@ -4,12 +4,18 @@ use r2r::tf2_msgs::msg::TFMessage;
 use r2r::visualization_msgs::msg::Marker;
 use r2r::Context;
 use r2r::QosProfile;
-use r2r_regular_markers::RegularMarkerServer;
+use r2r_regular_markers::{spawn_worker, MarkerState, RegularMarkerServer, WorkerHandle};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
 
 pub static NODE_ID: &'static str = "simple_marker";
 
+/// How often the static frame broadcaster re-publishes its transforms.
+/// Adjustable at runtime via `WorkerHandle::set_interval`.
+const STATIC_BROADCAST_INTERVAL_MS: u64 = 20;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FrameData {
     pub parent_frame_id: String,
@@ -51,13 +57,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let node = r2r::Node::create(context, "asdf", "")?;
     let arc_node = Arc::new(Mutex::new(node));
 
+    let server = RegularMarkerServer::new("simple_marker", &arc_node);
+
     // We need to publish a frame where the marker can be placed
     let broadcasted_frames = Arc::new(Mutex::new(make_initial_tf()));
     let arc_node_clone = arc_node.clone();
-    let static_pub_timer = arc_node_clone
-        .lock()
-        .unwrap()
-        .create_wall_timer(std::time::Duration::from_millis(20))?;
     let static_frame_broadcaster = arc_node_clone
         .lock()
         .unwrap()
@@ -66,21 +70,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             QosProfile::transient_local(QosProfile::default()),
         )?;
     let broadcasted_frames_clone = broadcasted_frames.clone();
-    tokio::task::spawn(async move {
-        match static_frame_broadcaster_callback(
-            static_frame_broadcaster,
-            static_pub_timer,
-            &broadcasted_frames_clone,
-        )
-        .await
-        {
-            Ok(()) => (),
-            Err(e) => r2r::log_error!(NODE_ID, "Active frame broadcaster failed with: '{}'.", e),
-        };
-    });
-
-    let arc_node_clone = arc_node.clone();
-    let server = RegularMarkerServer::new("simple_marker", arc_node_clone);
+    // `subscribe()` lets the broadcaster mirror live markers into TF
+    // frames without going through `insert`/`delete` itself.
+    let marker_rx = server.subscribe();
+    // Kept alive for the lifetime of the node: dropping a `WorkerHandle`
+    // cancels its worker, so letting this go out of scope would silently
+    // stop the broadcaster.
+    let _static_broadcaster_worker: WorkerHandle = spawn_worker(
+        Duration::from_millis(STATIC_BROADCAST_INTERVAL_MS),
+        move |mut control| async move {
+            static_frame_broadcaster_callback(
+                static_frame_broadcaster,
+                &mut control,
+                &broadcasted_frames_clone,
+                marker_rx,
+            )
+            .await
+        },
+    );
 
     let mut marker = Marker::default();
     marker.header.frame_id = "base_link".to_string();
@@ -133,15 +140,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-// Broadcast static frames
+// Broadcasts the hardcoded static frames, plus a TF frame per live marker
+// mirrored from `RegularMarkerServer::subscribe()`.
 async fn static_frame_broadcaster_callback(
     publisher: r2r::Publisher<TFMessage>,
-    mut timer: r2r::Timer,
+    control: &mut r2r_regular_markers::WorkerControl,
     frames: &Arc<Mutex<HashMap<String, FrameData>>>,
+    mut marker_rx: watch::Receiver<MarkerState>,
     // node_id: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+) {
     let mut x = 0.0;
     loop {
+        match control.next_tick_or(marker_rx.changed()).await {
+            r2r_regular_markers::WorkerWake::Stopped => break,
+            r2r_regular_markers::WorkerWake::Tick(_) => (),
+            r2r_regular_markers::WorkerWake::Extra(Ok(())) => (),
+            r2r_regular_markers::WorkerWake::Extra(Err(_)) => break,
+        }
+
         x = x + 0.001;
         let mut clock = r2r::Clock::create(r2r::ClockType::RosTime).unwrap();
         let now = clock.get_now().unwrap();
@@ -171,13 +187,36 @@ async fn static_frame_broadcaster_callback(
             Some(true) | None => (),
         });
 
+        for (name, entry) in marker_rx.borrow().iter() {
+            if entry.deleted {
+                continue;
+            }
+            updated_transforms.push(TransformStamped {
+                header: Header {
+                    stamp: time_stamp.clone(),
+                    frame_id: entry.marker.header.frame_id.clone(),
+                },
+                child_frame_id: format!("marker/{}", name),
+                transform: Transform {
+                    translation: Vector3 {
+                        x: entry.marker.pose.position.x,
+                        y: entry.marker.pose.position.y,
+                        z: entry.marker.pose.position.z,
+                    },
+                    rotation: entry.marker.pose.orientation.clone(),
+                },
+            });
+        }
+
+        let published_count = updated_transforms.len();
         let msg = TFMessage {
             transforms: updated_transforms,
         };
 
         match publisher.publish(&msg) {
-            Ok(()) => (),
+            Ok(()) => control.record_publish(published_count),
             Err(e) => {
+                control.record_error();
                 r2r::log_error!(
                     NODE_ID,
                     "Static broadcaster failed to send a message with: '{}'",
@@ -185,6 +224,5 @@ async fn static_frame_broadcaster_callback(
                 );
             }
         };
-        timer.tick().await?;
     }
 }
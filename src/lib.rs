@@ -1,7 +1,68 @@
+mod persistence;
+mod worker;
+
+use persistence::PersistedState;
 use r2r::visualization_msgs::msg::{Marker, MarkerArray};
-use r2r::{Publisher, QosProfile, Timer};
+use r2r::{Publisher, QosProfile};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+pub use worker::{spawn_worker, WorkerControl, WorkerHandle, WorkerState, WorkerStatus, WorkerWake};
+
+/// How often the publisher republishes every live marker as a "keyframe",
+/// even if nothing changed.
+///
+/// The publisher otherwise only wakes up when the marker state actually
+/// changes, so this heartbeat is what lets a late-joining `transient_local`
+/// subscriber (e.g. RViz starting after the server) recover the full marker
+/// state instead of waiting forever for the next edit.
+const HEARTBEAT_INTERVAL_MS: u64 = 1000;
+
+/// How long a tombstone is kept around after a delete before it is purged
+/// from the map entirely.
+///
+/// Retaining it for this long gives a delayed, older-timestamped re-insert
+/// from another writer a chance to arrive and be correctly rejected by the
+/// LWW rule; purging it only after the TTL bounds how long deleted markers
+/// keep taking up space.
+const TOMBSTONE_TTL_MS: u128 = 30_000;
+
+/// Disambiguates `server_id`s minted within the same process (see
+/// [`generate_server_id`]); not itself a `server_id`.
+static NEXT_SERVER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Generates the tie-break id used in `(timestamp, server_id)` ordering
+/// when two writers stamp an update with the same wall-clock millisecond.
+///
+/// This has to be unique across processes and machines, not just within
+/// one process: several nodes publishing markers concurrently is exactly
+/// the scenario `merge` exists for, and a plain per-process counter
+/// restarting at 1 on every launch would make two freshly started nodes
+/// collide on every tie, each then keeping its own value forever instead
+/// of converging. In the absence of a random-number dependency, this
+/// folds together the process id, a nanosecond startup timestamp, a
+/// per-process sequence number, and a stack address (for its ASLR
+/// entropy) through a hasher as a dependency-free stand-in for a random
+/// `u64`.
+fn generate_server_id() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    std::process::id().hash(&mut hasher);
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos()
+        .hash(&mut hasher);
+    NEXT_SERVER_ID.fetch_add(1, Ordering::Relaxed).hash(&mut hasher);
+    let stack_marker = 0u8;
+    (&stack_marker as *const u8 as usize).hash(&mut hasher);
+    hasher.finish()
+}
 
 /// Represents the type of update to perform on a marker.
 #[derive(Clone, Debug)]
@@ -17,15 +78,53 @@ pub enum UpdateType {
 struct UpdateContext {
     pub update_type: UpdateType,
     pub marker: Marker,
+    pub timestamp: u128,
+    pub server_id: u64,
+    pub seq: u64,
 }
 
+/// A single last-writer-wins entry in a `MarkerState`.
+///
+/// A `deleted` entry is a tombstone: it still carries the marker's last
+/// known `ns`/`id` (so a `DELETE` can reference them) and timestamp (so the
+/// LWW rule can tell a stale re-insert from a newer one), but no longer
+/// represents a marker that should be drawn.
+#[derive(Clone, Debug)]
+pub struct LwwMarker {
+    pub marker: Marker,
+    pub timestamp: u128,
+    pub server_id: u64,
+    /// Per-server sequence number from the stamp that produced this entry,
+    /// used to break ties between two updates from the *same* server
+    /// landing in the same wall-clock millisecond (see [`RegularMarkerServer::stamp`]).
+    pub seq: u64,
+    pub deleted: bool,
+}
+
+impl LwwMarker {
+    /// Whether `self` should be replaced by an update stamped with
+    /// `(timestamp, server_id, seq)`, i.e. that update is strictly newer.
+    fn superseded_by(&self, timestamp: u128, server_id: u64, seq: u64) -> bool {
+        (timestamp, server_id, seq) > (self.timestamp, self.server_id, self.seq)
+    }
+}
+
+/// The exportable last-writer-wins marker state of a `RegularMarkerServer`,
+/// as folded by [`RegularMarkerServer::merge`].
+pub type MarkerState = HashMap<String, LwwMarker>;
+
 /// A server that manages and publishes markers regularly.
 #[derive(Clone)]
 pub struct RegularMarkerServer {
     // pub topic_namespace: String,
     pub topic: String,
-    marker_contexts: Arc<Mutex<HashMap<String, Marker>>>,
+    server_id: u64,
+    next_seq: Arc<AtomicU64>,
+    marker_state: watch::Sender<MarkerState>,
     pending_updates: Arc<Mutex<HashMap<String, UpdateContext>>>,
+    marker_ids: Arc<Mutex<HashMap<String, i32>>>,
+    next_marker_id: Arc<Mutex<i32>>,
+    worker: Arc<WorkerHandle>,
 }
 
 impl RegularMarkerServer {
@@ -37,6 +136,49 @@ impl RegularMarkerServer {
     /// * `topic_name` - The name of the ROS topic.
     /// * `node` - A reference to the ROS node.
     pub fn new(topic: &str, node: &Arc<Mutex<r2r::Node>>) -> Self {
+        Self::build(topic, node, MarkerState::new(), None)
+    }
+
+    /// Creates a new `RegularMarkerServer` that persists its marker state to
+    /// disk so it survives a node crash or restart.
+    ///
+    /// On startup, a previously persisted snapshot at `path` (if any) is
+    /// loaded and used to seed the marker state before the publisher starts.
+    /// From then on, a dedicated background task watches the marker state
+    /// and writes each new snapshot back to `path`, one at a time, so
+    /// writes triggered by a burst of [`apply_changes`](Self::apply_changes)
+    /// calls can never land on disk out of order.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The name of the ROS topic.
+    /// * `node` - A reference to the ROS node.
+    /// * `path` - Where to load the snapshot from and persist it to.
+    pub fn with_persistence(topic: &str, node: &Arc<Mutex<r2r::Node>>, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let initial_state = persistence::load(&path)
+            .map(PersistedState::into_marker_state)
+            .unwrap_or_else(|e| {
+                r2r::log_warn!(
+                    "asdf",
+                    "Could not load persisted markers from '{}': {}. Starting empty.",
+                    path.display(),
+                    e
+                );
+                MarkerState::new()
+            });
+
+        Self::build(topic, node, initial_state, Some(path))
+    }
+
+    /// Shared constructor body for [`new`](Self::new) and
+    /// [`with_persistence`](Self::with_persistence).
+    fn build(
+        topic: &str,
+        node: &Arc<Mutex<r2r::Node>>,
+        initial_state: MarkerState,
+        persist_path: Option<PathBuf>,
+    ) -> Self {
         let publisher_topic = format!("{}", topic);
         let mut publisher_qos = QosProfile::default();
         publisher_qos.depth = 100;
@@ -48,44 +190,206 @@ impl RegularMarkerServer {
             .create_publisher::<MarkerArray>(&publisher_topic, publisher_qos)
             .expect("Failed to create publisher");
 
-        // Create a timer for periodic publishing.
-        let timer = node
-            .lock()
-            .unwrap()
-            .create_wall_timer(std::time::Duration::from_millis(20))
-            .unwrap();
-
-        let marker_contexts = Arc::new(Mutex::new(HashMap::new()));
-        let pending_updates = Arc::new(Mutex::new(HashMap::new()));
+        let marker_ids = initial_state
+            .iter()
+            .map(|(name, entry)| (name.clone(), entry.marker.id))
+            .collect::<HashMap<String, i32>>();
+        let next_marker_id = marker_ids.values().copied().max().map_or(0, |id| id + 1);
 
-        let marker_contexts_clone = marker_contexts.clone();
+        let (marker_state, marker_rx) = watch::channel(initial_state);
 
-        // Spawn a task to publish markers periodically.
-        tokio::task::spawn(async move {
-            match Self::marker_array_publisher(marker_contexts_clone, publisher, timer).await {
-                Ok(()) => (),
-                Err(e) => r2r::log_error!("asdf", "Marker array publisher failed with: '{}'.", e),
-            };
-        });
+        if let Some(path) = persist_path {
+            Self::spawn_persister(Arc::new(path), marker_state.subscribe());
+        }
 
-        let marker_contexts_clone = marker_contexts.clone();
-        let pending_updates_clone = pending_updates.clone();
+        let worker = Self::spawn_publisher(marker_state.clone(), marker_rx, publisher);
 
         Self {
             // topic_namespace: topic_namespace.to_string(),
             topic: topic.to_string(),
-            marker_contexts: marker_contexts_clone,
-            pending_updates: pending_updates_clone,
+            server_id: generate_server_id(),
+            next_seq: Arc::new(AtomicU64::new(0)),
+            marker_state,
+            pending_updates: Arc::new(Mutex::new(HashMap::new())),
+            marker_ids: Arc::new(Mutex::new(marker_ids)),
+            next_marker_id: Arc::new(Mutex::new(next_marker_id)),
+            worker: Arc::new(worker),
         }
     }
 
+    /// Returns a handle for controlling and inspecting the background
+    /// worker that publishes this server's markers: pause/resume it,
+    /// change its publish interval at runtime, force a one-shot full
+    /// republish via [`WorkerHandle::republish`], or read its
+    /// [`WorkerStatus`].
+    pub fn worker(&self) -> &WorkerHandle {
+        &self.worker
+    }
+
+    /// Spawns the background task that persists marker state for a server
+    /// created via [`with_persistence`](Self::with_persistence).
+    ///
+    /// Writes happen one at a time: the task waits for `marker_rx` to
+    /// report a change, then awaits the write (atomic, via a temp file
+    /// plus rename, so a crash mid-write never leaves a torn snapshot on
+    /// disk) before looping back to wait for the next one. A single task
+    /// awaiting each write in turn - rather than one `spawn_blocking` per
+    /// [`apply_changes`](Self::apply_changes) call racing the others on
+    /// tokio's blocking pool - is what guarantees a burst of changes can
+    /// never be written to disk out of order; if several changes land
+    /// while a write is in flight, `watch` coalesces them so the task
+    /// picks up the latest snapshot once it's ready for the next one.
+    fn spawn_persister(path: Arc<PathBuf>, mut marker_rx: watch::Receiver<MarkerState>) {
+        tokio::task::spawn(async move {
+            while marker_rx.changed().await.is_ok() {
+                let state = PersistedState::from_marker_state(&marker_rx.borrow());
+                let write_path = path.clone();
+
+                match tokio::task::spawn_blocking(move || persistence::save(&write_path, &state))
+                    .await
+                {
+                    Ok(Ok(())) => (),
+                    Ok(Err(e)) => r2r::log_error!(
+                        "asdf",
+                        "Failed to persist markers to '{}': {}.",
+                        path.display(),
+                        e
+                    ),
+                    Err(e) => r2r::log_error!(
+                        "asdf",
+                        "Persistence task for '{}' panicked: {}.",
+                        path.display(),
+                        e
+                    ),
+                }
+            }
+        });
+    }
+
+    /// Subscribes to the server's marker state.
+    ///
+    /// The returned receiver always has access to the current snapshot and
+    /// can be awaited with `changed()` to react to updates without
+    /// re-locking or cloning the whole map on every access - e.g. for a
+    /// task that mirrors marker state into another representation (TF
+    /// frames, a UI, a second transport) without going through
+    /// [`insert`](Self::insert)/[`delete`](Self::delete) itself. See
+    /// `examples/simple_marker.rs`'s static frame broadcaster for a
+    /// concrete consumer that does exactly this.
+    pub fn subscribe(&self) -> watch::Receiver<MarkerState> {
+        self.marker_state.subscribe()
+    }
+
+    /// Exports the current last-writer-wins marker state, including
+    /// tombstones, for transmission to another server's [`merge`](Self::merge).
+    pub fn export(&self) -> MarkerState {
+        self.marker_state.borrow().clone()
+    }
+
+    /// Folds another server's exported state into this one.
+    ///
+    /// For every name, the entry with the greatest `(timestamp, server_id,
+    /// seq)` wins, so a concurrent insert and delete of the same marker
+    /// converge to the same result on every server regardless of arrival
+    /// order: a delayed re-insert carrying an older timestamp cannot
+    /// resurrect a marker that a newer delete already removed, and a
+    /// delayed delete cannot clobber a newer insert.
+    ///
+    /// An incoming entry that wins also updates [`id_for`](Self::id_for)'s
+    /// `marker_ids`/`next_marker_id` bookkeeping to match its `(ns, id)`.
+    /// Without that, a later local [`insert`](Self::insert) for the same
+    /// `name` would find no record of it, mint a fresh `(ns, id)` under
+    /// that `name` key, and silently orphan the merged marker's id in
+    /// RViz - the publisher's vanished-name cleanup only diffs by `name`,
+    /// so a changed `id` under the same `name` never gets an explicit
+    /// `DELETE`.
+    pub fn merge(&self, other: MarkerState) {
+        let mut marker_ids = self.marker_ids.lock().unwrap();
+        let mut next_marker_id = self.next_marker_id.lock().unwrap();
+
+        self.marker_state.send_modify(|marker_contexts| {
+            for (name, incoming) in other {
+                match marker_contexts.get(&name) {
+                    Some(existing)
+                        if !existing.superseded_by(incoming.timestamp, incoming.server_id, incoming.seq) => {}
+                    _ => {
+                        if incoming.marker.id >= *next_marker_id {
+                            *next_marker_id = incoming.marker.id + 1;
+                        }
+                        marker_ids.insert(name.clone(), incoming.marker.id);
+                        marker_contexts.insert(name, incoming);
+                    }
+                }
+            }
+
+            Self::prune_expired_tombstones(marker_contexts);
+        });
+    }
+
+    /// Returns a `(timestamp, server_id, seq)` stamp for an update made
+    /// right now: wall-clock milliseconds since the epoch, this server's
+    /// tie-break id, and a per-server sequence number that increases on
+    /// every call.
+    ///
+    /// The sequence number exists because two stamps from *this* server
+    /// can otherwise land in the same millisecond - e.g. back-to-back
+    /// `insert`/`apply_changes` and `delete`/`apply_changes` calls in a
+    /// tight loop - in which case `(timestamp, server_id)` alone would tie
+    /// and [`LwwMarker::superseded_by`]'s strict `>` would silently drop
+    /// the later update.
+    fn stamp(&self) -> (u128, u64, u64) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        (timestamp, self.server_id, seq)
+    }
+
+    /// Drops tombstones older than [`TOMBSTONE_TTL_MS`] so deleted markers
+    /// don't accumulate forever.
+    fn prune_expired_tombstones(marker_contexts: &mut MarkerState) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+
+        marker_contexts.retain(|_, entry| {
+            !entry.deleted || now.saturating_sub(entry.timestamp) < TOMBSTONE_TTL_MS
+        });
+    }
+
+    /// Returns the stable `(ns, id)` pair for `name`, assigning a fresh one
+    /// the first time it is seen.
+    ///
+    /// RViz keys markers by `(ns, id)` rather than by name, so the same pair
+    /// must be reused across inserts or the viewer will treat an update as a
+    /// brand new marker instead of a modification.
+    fn id_for(&self, name: &str) -> (String, i32) {
+        let mut marker_ids = self.marker_ids.lock().unwrap();
+
+        let id = *marker_ids.entry(name.to_string()).or_insert_with(|| {
+            let mut next_marker_id = self.next_marker_id.lock().unwrap();
+            let id = *next_marker_id;
+            *next_marker_id += 1;
+            id
+        });
+
+        (name.to_string(), id)
+    }
+
     /// Inserts a new marker.
     ///
     /// # Arguments
     ///
     /// * `name` - The unique name of the marker.
     /// * `marker` - The marker data to insert.
-    pub fn insert(&self, name: &str, marker: Marker) {
+    pub fn insert(&self, name: &str, mut marker: Marker) {
+        let (ns, id) = self.id_for(name);
+        marker.ns = ns;
+        marker.id = id;
+
+        let (timestamp, server_id, seq) = self.stamp();
         let mut pending_updates = self.pending_updates.lock().unwrap();
 
         // Add or update the pending update for the marker.
@@ -95,10 +399,16 @@ impl RegularMarkerServer {
                 .or_insert_with(|| UpdateContext {
                     update_type: UpdateType::Add,
                     marker: marker.clone(),
+                    timestamp,
+                    server_id,
+                    seq,
                 });
 
         update_context.update_type = UpdateType::Add;
         update_context.marker = marker;
+        update_context.timestamp = timestamp;
+        update_context.server_id = server_id;
+        update_context.seq = seq;
 
         println!("Marker added with name '{}'", name);
     }
@@ -109,23 +419,42 @@ impl RegularMarkerServer {
     ///
     /// * `name` - The unique name of the marker to delete.
     pub fn delete(&self, name: &str) {
-        let marker_contexts = self.marker_contexts.lock().unwrap();
+        let marker_context = self
+            .marker_state
+            .borrow()
+            .get(name)
+            .filter(|entry| !entry.deleted)
+            .map(|entry| entry.marker.clone());
+        let (timestamp, server_id, seq) = self.stamp();
         let mut pending_updates = self.pending_updates.lock().unwrap();
 
-        if let Some(marker_context) = marker_contexts.get(name) {
+        if let Some(marker_context) = marker_context {
             pending_updates.insert(
                 name.to_string(),
                 UpdateContext {
                     update_type: UpdateType::Delete,
-                    marker: marker_context.clone(),
+                    marker: marker_context,
+                    timestamp,
+                    server_id,
+                    seq,
                 },
             );
         }
     }
 
     /// Applies pending updates to markers.
+    ///
+    /// This folds every pending update into a new marker-state snapshot and
+    /// publishes it on the `watch` channel in one step, so the publisher
+    /// task (and any `subscribe`r) observes the change by waking up on
+    /// `changed()` rather than by polling a shared, lockable map. Each
+    /// update only takes effect if it is newer, by `(timestamp, server_id,
+    /// seq)`, than what is already stored, which is what makes concurrent
+    /// writers on the same marker namespace converge instead of racing. If this
+    /// server was created with [`with_persistence`](Self::with_persistence),
+    /// the same `watch` publish also wakes the persistence task, which
+    /// writes the resulting snapshot to disk in the background.
     pub fn apply_changes(&self) {
-        let mut marker_contexts = self.marker_contexts.lock().unwrap();
         let mut pending_updates = self.pending_updates.lock().unwrap();
 
         if pending_updates.is_empty() {
@@ -133,86 +462,257 @@ impl RegularMarkerServer {
             return;
         }
 
-        for (name, update_context) in pending_updates.iter() {
-            match update_context.update_type {
-                UpdateType::Add => {
-                    marker_contexts.entry(name.clone()).or_insert_with(|| {
-                        let mut marker_context = update_context.marker.clone();
-                        marker_context.action = Marker::ADD as i32;
-                        marker_context
+        self.marker_state.send_modify(|marker_contexts| {
+            for (name, update_context) in pending_updates.iter() {
+                let stamp_is_newer = marker_contexts
+                    .get(name)
+                    .map_or(true, |existing| {
+                        existing.superseded_by(
+                            update_context.timestamp,
+                            update_context.server_id,
+                            update_context.seq,
+                        )
                     });
+                if !stamp_is_newer {
+                    continue;
                 }
-                UpdateType::Modify => {
-                    if let Some(marker_context) = marker_contexts.get_mut(name) {
-                        marker_context.pose = update_context.marker.pose.clone();
-                        marker_context.header = update_context.marker.header.clone();
-                        marker_context.action = Marker::MODIFY as i32;
-                    } else {
-                        println!("Pending modify update for non-existing marker '{}'.", name);
+
+                match update_context.update_type {
+                    UpdateType::Add => {
+                        let mut marker = update_context.marker.clone();
+                        marker.action = Marker::ADD as i32;
+                        marker_contexts.insert(
+                            name.clone(),
+                            LwwMarker {
+                                marker,
+                                timestamp: update_context.timestamp,
+                                server_id: update_context.server_id,
+                                seq: update_context.seq,
+                                deleted: false,
+                            },
+                        );
                     }
-                }
-                UpdateType::Delete => {
-                    if let Some(marker_context) = marker_contexts.get_mut(name) {
-                        marker_context.action = Marker::DELETE as i32;
-                    } else {
-                        println!("Pending delete update for non-existing marker '{}'.", name);
+                    UpdateType::Modify => {
+                        if let Some(entry) = marker_contexts.get_mut(name) {
+                            entry.marker.pose = update_context.marker.pose.clone();
+                            entry.marker.header = update_context.marker.header.clone();
+                            entry.marker.action = Marker::MODIFY as i32;
+                            entry.timestamp = update_context.timestamp;
+                            entry.server_id = update_context.server_id;
+                            entry.seq = update_context.seq;
+                        } else {
+                            println!("Pending modify update for non-existing marker '{}'.", name);
+                        }
                     }
-                }
-                UpdateType::DeleteAll => {
-                    for marker_context in marker_contexts.values_mut() {
-                        marker_context.action = Marker::DELETEALL as i32;
+                    UpdateType::Delete => {
+                        let mut marker = update_context.marker.clone();
+                        marker.action = Marker::DELETE as i32;
+                        marker_contexts.insert(
+                            name.clone(),
+                            LwwMarker {
+                                marker,
+                                timestamp: update_context.timestamp,
+                                server_id: update_context.server_id,
+                                seq: update_context.seq,
+                                deleted: true,
+                            },
+                        );
+                    }
+                    UpdateType::DeleteAll => {
+                        for entry in marker_contexts.values_mut() {
+                            entry.marker.action = Marker::DELETEALL as i32;
+                            entry.timestamp = update_context.timestamp;
+                            entry.server_id = update_context.server_id;
+                            entry.seq = update_context.seq;
+                            entry.deleted = true;
+                        }
                     }
                 }
             }
-        }
+
+            Self::prune_expired_tombstones(marker_contexts);
+        });
 
         pending_updates.clear();
     }
 
-    /// Publishes marker arrays periodically.
+    /// Computes a stable digest over the marker fields that matter to RViz.
     ///
-    /// # Arguments
+    /// Two markers that would render identically must hash identically, so
+    /// only the fields that affect what is drawn (and where) are included -
+    /// not `action`, which records how the entry was last updated rather
+    /// than what it looks like, and not `timestamp`/`server_id`/`seq`, which
+    /// are LWW bookkeeping rather than render state.
+    fn marker_digest(entry: &LwwMarker) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        entry.deleted.hash(&mut hasher);
+        if entry.deleted {
+            entry.marker.ns.hash(&mut hasher);
+            entry.marker.id.hash(&mut hasher);
+            return hasher.finish();
+        }
+
+        let marker = &entry.marker;
+        marker.ns.hash(&mut hasher);
+        marker.id.hash(&mut hasher);
+        marker.type_.hash(&mut hasher);
+        marker.header.frame_id.hash(&mut hasher);
+        marker.header.stamp.sec.hash(&mut hasher);
+        marker.header.stamp.nanosec.hash(&mut hasher);
+
+        Self::hash_f64(&mut hasher, marker.pose.position.x);
+        Self::hash_f64(&mut hasher, marker.pose.position.y);
+        Self::hash_f64(&mut hasher, marker.pose.position.z);
+        Self::hash_f64(&mut hasher, marker.pose.orientation.x);
+        Self::hash_f64(&mut hasher, marker.pose.orientation.y);
+        Self::hash_f64(&mut hasher, marker.pose.orientation.z);
+        Self::hash_f64(&mut hasher, marker.pose.orientation.w);
+
+        Self::hash_f64(&mut hasher, marker.scale.x);
+        Self::hash_f64(&mut hasher, marker.scale.y);
+        Self::hash_f64(&mut hasher, marker.scale.z);
+
+        Self::hash_f32(&mut hasher, marker.color.r);
+        Self::hash_f32(&mut hasher, marker.color.g);
+        Self::hash_f32(&mut hasher, marker.color.b);
+        Self::hash_f32(&mut hasher, marker.color.a);
+
+        marker.points.len().hash(&mut hasher);
+        for point in &marker.points {
+            Self::hash_f64(&mut hasher, point.x);
+            Self::hash_f64(&mut hasher, point.y);
+            Self::hash_f64(&mut hasher, point.z);
+        }
+
+        hasher.finish()
+    }
+
+    fn hash_f64(hasher: &mut impl Hasher, value: f64) -> () {
+        value.to_bits().hash(hasher)
+    }
+
+    fn hash_f32(hasher: &mut impl Hasher, value: f32) -> () {
+        value.to_bits().hash(hasher)
+    }
+
+    /// Builds a synthetic `DELETE` marker for a tombstoned name or a name
+    /// that has disappeared from the marker state, reusing its last known
+    /// `(ns, id)` pair so RViz can match it up with the marker it is removing.
+    fn delete_marker(ns: String, id: i32) -> Marker {
+        let mut marker = Marker::default();
+        marker.ns = ns;
+        marker.id = id;
+        marker.action = Marker::DELETE as i32;
+        marker
+    }
+
+    /// Spawns the supervised worker that publishes marker arrays as the
+    /// shared marker state changes.
+    ///
+    /// Each iteration waits for either `marker_rx` to report a change, the
+    /// worker's tick interval (by default [`HEARTBEAT_INTERVAL_MS`],
+    /// adjustable at runtime via [`WorkerHandle::set_interval`]) to
+    /// elapse, or [`WorkerHandle::republish`] to be called - the publisher
+    /// stays event-driven rather than busy-polling the marker state, with
+    /// the worker's own ticking standing in for the periodic heartbeat.
+    /// Only entries whose digest differs from what was last sent are
+    /// published on a plain change - a tombstone renders as a `DELETE`,
+    /// everything else as the live marker - plus synthetic `DELETE`
+    /// markers for names whose tombstone has since been purged by its
+    /// TTL. A tick or a forced republish instead republishes every live
+    /// marker as a keyframe so a subscriber that joins late (or missed a
+    /// message) still converges on the correct state.
     ///
-    /// * `marker_contexts` - Shared marker contexts.
-    /// * `publisher` - The publisher to publish marker arrays.
-    /// * `timer` - Timer for periodic publishing.
-    async fn marker_array_publisher(
-        marker_contexts: Arc<Mutex<HashMap<String, Marker>>>,
+    /// A tick also sweeps [`prune_expired_tombstones`](Self::prune_expired_tombstones)
+    /// over the shared state via `marker_state`, so a server that goes
+    /// idle after a delete still expires the tombstone on schedule instead
+    /// of waiting for some future local or merged write to trigger the
+    /// sweep as a side effect.
+    fn spawn_publisher(
+        marker_state: watch::Sender<MarkerState>,
+        marker_rx: watch::Receiver<MarkerState>,
         publisher: Publisher<MarkerArray>,
-        mut timer: Timer,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        loop {
-            let mut markers = marker_contexts.lock().unwrap().clone();
-            let mut update_msg = MarkerArray::default();
-
-            // Collect markers to publish.
-            for (_, marker) in &markers {
-                update_msg.markers.push(marker.clone());
-            }
+    ) -> WorkerHandle {
+        worker::spawn_worker(
+            Duration::from_millis(HEARTBEAT_INTERVAL_MS),
+            move |mut control| async move {
+                let mut marker_rx = marker_rx;
+                let mut last_digests: HashMap<String, u64> = HashMap::new();
+                let mut last_ids: HashMap<String, (String, i32)> = HashMap::new();
+
+                loop {
+                    let keyframe = match control.next_tick_or(marker_rx.changed()).await {
+                        worker::WorkerWake::Stopped => break,
+                        worker::WorkerWake::Tick(_) => true,
+                        worker::WorkerWake::Extra(Ok(())) => false,
+                        worker::WorkerWake::Extra(Err(_)) => break,
+                    };
+
+                    if keyframe {
+                        marker_state.send_if_modified(|marker_contexts| {
+                            let before = marker_contexts.len();
+                            Self::prune_expired_tombstones(marker_contexts);
+                            marker_contexts.len() != before
+                        });
+                    }
+
+                    let marker_contexts = marker_rx.borrow().clone();
+
+                    let mut update_msg = MarkerArray::default();
+                    let mut current_digests: HashMap<String, u64> =
+                        HashMap::with_capacity(marker_contexts.len());
+                    let mut current_ids: HashMap<String, (String, i32)> =
+                        HashMap::with_capacity(marker_contexts.len());
+
+                    for (name, entry) in &marker_contexts {
+                        let digest = Self::marker_digest(entry);
+                        current_digests.insert(name.clone(), digest);
+                        current_ids
+                            .insert(name.clone(), (entry.marker.ns.clone(), entry.marker.id));
 
-            // Publish the marker array.
-            publisher
-                .publish(&update_msg)
-                .expect("Failed to publish update");
-
-            // Update marker contexts based on actions.
-            for (name, marker) in markers.clone().iter() {
-                match marker.action {
-                    2 => {
-                        // Remove markers marked for deletion.
-                        let _ = markers.remove(name);
+                        let changed = last_digests.get(name) != Some(&digest);
+                        if !changed && !keyframe {
+                            continue;
+                        }
+
+                        if entry.deleted {
+                            update_msg.markers.push(Self::delete_marker(
+                                entry.marker.ns.clone(),
+                                entry.marker.id,
+                            ));
+                        } else {
+                            update_msg.markers.push(entry.marker.clone());
+                        }
                     }
-                    3 => {
-                        // Clear all markers if delete all action is set.
-                        markers.clear();
+
+                    // A tombstone purged by its TTL needs an explicit
+                    // DELETE so RViz drops it instead of leaving a stale
+                    // marker.
+                    for (name, (ns, id)) in &last_ids {
+                        if !current_ids.contains_key(name) {
+                            update_msg.markers.push(Self::delete_marker(ns.clone(), *id));
+                        }
                     }
-                    _ => (),
-                }
-            }
 
-            *marker_contexts.lock().unwrap() = markers;
+                    if !update_msg.markers.is_empty() {
+                        match publisher.publish(&update_msg) {
+                            Ok(()) => control.record_publish(update_msg.markers.len()),
+                            Err(e) => {
+                                control.record_error();
+                                r2r::log_error!(
+                                    "asdf",
+                                    "Marker array publisher failed to publish an update: '{}'.",
+                                    e
+                                );
+                            }
+                        }
+                    }
 
-            timer.tick().await?;
-        }
+                    last_digests = current_digests;
+                    last_ids = current_ids;
+                }
+            },
+        )
     }
 }
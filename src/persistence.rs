@@ -0,0 +1,221 @@
+//! Disk persistence for `RegularMarkerServer`'s marker state.
+//!
+//! The r2r message types generated from `.msg` files don't implement
+//! `serde::{Serialize, Deserialize}`, so this module mirrors a subset of the
+//! marker fields into plain structs that do, and converts between them and
+//! the server's `MarkerState`. Only `ns`, `id`, `type_`, the header's
+//! `frame_id`, `pose`, `scale`, `color` and `points` are persisted; every
+//! other field (`text`, `mesh_resource`, per-point `colors`, `lifetime`,
+//! `frame_locked`, the header's `stamp`, ...) comes back as its default after
+//! a reload.
+
+use crate::{LwwMarker, MarkerState};
+use r2r::geometry_msgs::msg::{Point, Pose, Quaternion, Vector3};
+use r2r::visualization_msgs::msg::Marker;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+#[derive(Serialize, Deserialize)]
+struct PersistedPoint {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedPose {
+    position: PersistedPoint,
+    orientation: PersistedPoint,
+    orientation_w: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedColor {
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedMarker {
+    ns: String,
+    id: i32,
+    type_: i32,
+    frame_id: String,
+    pose: PersistedPose,
+    scale: PersistedPoint,
+    color: PersistedColor,
+    points: Vec<PersistedPoint>,
+}
+
+impl From<&Marker> for PersistedMarker {
+    fn from(marker: &Marker) -> Self {
+        PersistedMarker {
+            ns: marker.ns.clone(),
+            id: marker.id,
+            type_: marker.type_,
+            frame_id: marker.header.frame_id.clone(),
+            pose: PersistedPose {
+                position: PersistedPoint {
+                    x: marker.pose.position.x,
+                    y: marker.pose.position.y,
+                    z: marker.pose.position.z,
+                },
+                orientation: PersistedPoint {
+                    x: marker.pose.orientation.x,
+                    y: marker.pose.orientation.y,
+                    z: marker.pose.orientation.z,
+                },
+                orientation_w: marker.pose.orientation.w,
+            },
+            scale: PersistedPoint {
+                x: marker.scale.x,
+                y: marker.scale.y,
+                z: marker.scale.z,
+            },
+            color: PersistedColor {
+                r: marker.color.r,
+                g: marker.color.g,
+                b: marker.color.b,
+                a: marker.color.a,
+            },
+            points: marker
+                .points
+                .iter()
+                .map(|p| PersistedPoint {
+                    x: p.x,
+                    y: p.y,
+                    z: p.z,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl PersistedMarker {
+    fn into_marker(self, deleted: bool) -> Marker {
+        let mut marker = Marker::default();
+        marker.ns = self.ns;
+        marker.id = self.id;
+        marker.type_ = self.type_;
+        marker.action = if deleted {
+            Marker::DELETE as i32
+        } else {
+            Marker::ADD as i32
+        };
+        marker.header.frame_id = self.frame_id;
+        marker.pose = Pose {
+            position: Point {
+                x: self.pose.position.x,
+                y: self.pose.position.y,
+                z: self.pose.position.z,
+            },
+            orientation: Quaternion {
+                x: self.pose.orientation.x,
+                y: self.pose.orientation.y,
+                z: self.pose.orientation.z,
+                w: self.pose.orientation_w,
+            },
+        };
+        marker.scale = Vector3 {
+            x: self.scale.x,
+            y: self.scale.y,
+            z: self.scale.z,
+        };
+        marker.color = r2r::std_msgs::msg::ColorRGBA {
+            r: self.color.r,
+            g: self.color.g,
+            b: self.color.b,
+            a: self.color.a,
+        };
+        marker.points = self
+            .points
+            .into_iter()
+            .map(|p| Point {
+                x: p.x,
+                y: p.y,
+                z: p.z,
+            })
+            .collect();
+        marker
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PersistedEntry {
+    marker: PersistedMarker,
+    timestamp: u128,
+    server_id: u64,
+    seq: u64,
+    deleted: bool,
+}
+
+/// The on-disk representation of a `MarkerState` snapshot.
+#[derive(Serialize, Deserialize, Default)]
+pub struct PersistedState(HashMap<String, PersistedEntry>);
+
+impl PersistedState {
+    pub fn from_marker_state(state: &MarkerState) -> Self {
+        PersistedState(
+            state
+                .iter()
+                .map(|(name, entry)| {
+                    (
+                        name.clone(),
+                        PersistedEntry {
+                            marker: PersistedMarker::from(&entry.marker),
+                            timestamp: entry.timestamp,
+                            server_id: entry.server_id,
+                            seq: entry.seq,
+                            deleted: entry.deleted,
+                        },
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    pub fn into_marker_state(self) -> MarkerState {
+        self.0
+            .into_iter()
+            .map(|(name, entry)| {
+                (
+                    name,
+                    LwwMarker {
+                        marker: entry.marker.into_marker(entry.deleted),
+                        timestamp: entry.timestamp,
+                        server_id: entry.server_id,
+                        seq: entry.seq,
+                        deleted: entry.deleted,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/// Loads a persisted snapshot from `path`.
+///
+/// Returns `Ok` with an empty snapshot if `path` doesn't exist yet, since
+/// that's the normal state on a node's very first run.
+pub fn load(path: &Path) -> io::Result<PersistedState> {
+    if !path.exists() {
+        return Ok(PersistedState::default());
+    }
+    let bytes = std::fs::read(path)?;
+    serde_json::from_slice(&bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Atomically writes `state` to `path` by writing to a sibling `.tmp` file
+/// and renaming it over `path`, so a crash mid-write never leaves a torn
+/// snapshot behind.
+pub fn save(path: &Path, state: &PersistedState) -> io::Result<()> {
+    let bytes = serde_json::to_vec_pretty(state)?;
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    std::fs::write(&tmp_path, bytes)?;
+    std::fs::rename(&tmp_path, path)
+}
@@ -0,0 +1,288 @@
+//! A small supervised background-task runner.
+//!
+//! Plain `tokio::task::spawn` gives you a detached task: nobody can pause
+//! it, nobody can tell whether it is still alive, and a panic inside it is
+//! silently swallowed unless something happens to be polling the
+//! `JoinHandle`. This module wraps that pattern once so every periodic
+//! task this crate spawns - the marker publisher, and anything else shaped
+//! like it - is instead controllable and inspectable through a
+//! [`WorkerHandle`]: pause/resume, change the tick interval at runtime,
+//! force an out-of-band tick, and read back a [`WorkerStatus`].
+//!
+//! The task body itself is still written by the caller as an ordinary
+//! `loop`; [`WorkerControl::next_tick`] and
+//! [`WorkerControl::next_tick_or`] only factor out the
+//! command-versus-timer `select!` that drives it, the latter also letting
+//! the loop keep its own event-driven wakeup (e.g. a `watch` channel)
+//! alongside the worker's control surface.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// A command sent to a running worker through its [`WorkerHandle`].
+#[derive(Clone, Copy, Debug)]
+enum WorkerCommand {
+    /// Stop ticking on the timer until a [`WorkerCommand::Resume`] arrives.
+    Pause,
+    /// Resume ticking after a [`WorkerCommand::Pause`].
+    Resume,
+    /// Replace the tick interval, effective from the next wait.
+    SetInterval(Duration),
+    /// Force a single tick right now, even while paused, with `force` set
+    /// so the caller's loop can do a full republish instead of a delta.
+    Republish,
+}
+
+/// The run state of a worker, as reported by [`WorkerHandle::status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkerState {
+    /// Ticking normally on its interval.
+    Active,
+    /// Paused via [`WorkerHandle::pause`]; still alive and listening for
+    /// commands.
+    Idle,
+    /// The task has returned or panicked and is no longer running.
+    Dead,
+}
+
+#[derive(Default)]
+struct WorkerCounters {
+    idle: AtomicBool,
+    published: AtomicU64,
+    last_publish_time_ms: AtomicU64,
+    errors: AtomicU64,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+/// A snapshot of a worker's health and activity, as returned by
+/// [`WorkerHandle::status`].
+#[derive(Clone, Copy, Debug)]
+pub struct WorkerStatus {
+    pub state: WorkerState,
+    /// Total items published across every tick so far (e.g. markers for
+    /// the marker publisher).
+    pub published: u64,
+    /// When the last successful publish happened, as milliseconds since
+    /// the Unix epoch, or `None` if none has happened yet.
+    pub last_publish_time_ms: Option<u128>,
+    /// Total number of ticks whose publish attempt failed.
+    pub errors: u64,
+}
+
+/// What woke a [`WorkerControl::next_tick_or`] wait, as returned to the
+/// caller's loop.
+pub enum WorkerWake<T> {
+    /// The regular tick interval elapsed, or [`WorkerHandle::republish`]
+    /// forced one out of band (`true`) - the caller should bypass whatever
+    /// delta/keyframe logic it normally applies on a forced tick.
+    Tick(bool),
+    /// The caller-supplied `extra` future resolved first, with its value.
+    Extra(T),
+    /// Every [`WorkerHandle`] has been dropped; the caller's loop should
+    /// treat this as a shutdown request and return.
+    Stopped,
+}
+
+/// The loop-side counterpart to a [`WorkerHandle`].
+///
+/// Owned by the spawned task. [`next_tick`](Self::next_tick) and
+/// [`next_tick_or`](Self::next_tick_or) wait for either the tick interval
+/// to elapse or a command from the handle to arrive, applying
+/// `Pause`/`Resume`/`SetInterval` themselves and surfacing `Republish`
+/// (and ordinary ticks) to the caller's loop. The caller reports back what
+/// happened via [`record_publish`](Self::record_publish) and
+/// [`record_error`](Self::record_error).
+pub struct WorkerControl {
+    command_rx: mpsc::UnboundedReceiver<WorkerCommand>,
+    counters: Arc<WorkerCounters>,
+    ticker: tokio::time::Interval,
+    paused: bool,
+}
+
+impl WorkerControl {
+    /// Waits for the next tick.
+    ///
+    /// Returns `None` once every [`WorkerHandle`] has been dropped, which
+    /// the caller's loop should treat as a shutdown request and return.
+    /// Otherwise returns `Some(force)`, where `force` is `true` when the
+    /// tick was requested via [`WorkerHandle::republish`] rather than the
+    /// regular interval, and should bypass whatever delta/keyframe logic
+    /// the caller normally applies.
+    pub async fn next_tick(&mut self) -> Option<bool> {
+        match self.next_tick_or(std::future::pending::<()>()).await {
+            WorkerWake::Tick(forced) => Some(forced),
+            WorkerWake::Extra(()) => unreachable!("pending() never resolves"),
+            WorkerWake::Stopped => None,
+        }
+    }
+
+    /// Like [`next_tick`](Self::next_tick), but also wakes immediately if
+    /// `extra` resolves first, without giving up the regular
+    /// timer/command handling in the meantime.
+    ///
+    /// This is what lets a caller keep an event-driven wakeup (e.g.
+    /// `watch::Receiver::changed()`) alongside the worker's own
+    /// pause/resume/interval/republish control surface, instead of having
+    /// to choose one or the other.
+    ///
+    /// `extra` is polled only while not [`paused`](WorkerHandle::pause),
+    /// same as the regular tick: a caller driving its whole publish loop
+    /// off `next_tick_or` would otherwise keep publishing on every `extra`
+    /// wakeup even while "paused", and [`status`](WorkerHandle::status)
+    /// would report [`WorkerState::Idle`] while that was happening. A
+    /// wakeup that becomes ready while paused isn't lost - `extra` just
+    /// isn't polled that iteration, so it resolves on the first poll after
+    /// [`resume`](WorkerHandle::resume).
+    pub async fn next_tick_or<F, T>(&mut self, extra: F) -> WorkerWake<T>
+    where
+        F: Future<Output = T>,
+    {
+        tokio::pin!(extra);
+        loop {
+            tokio::select! {
+                command = self.command_rx.recv() => {
+                    match command {
+                        Some(WorkerCommand::Pause) => {
+                            self.paused = true;
+                            self.counters.idle.store(true, Ordering::Relaxed);
+                        }
+                        Some(WorkerCommand::Resume) => {
+                            self.paused = false;
+                            self.counters.idle.store(false, Ordering::Relaxed);
+                        }
+                        Some(WorkerCommand::SetInterval(interval)) => {
+                            self.ticker = tokio::time::interval(interval);
+                        }
+                        Some(WorkerCommand::Republish) => return WorkerWake::Tick(true),
+                        None => return WorkerWake::Stopped,
+                    }
+                }
+                _ = self.ticker.tick(), if !self.paused => return WorkerWake::Tick(false),
+                value = &mut extra, if !self.paused => return WorkerWake::Extra(value),
+            }
+        }
+    }
+
+    /// Records that `count` items were published on this tick.
+    pub fn record_publish(&self, count: usize) {
+        self.counters.published.fetch_add(count as u64, Ordering::Relaxed);
+        self.counters
+            .last_publish_time_ms
+            .store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// Records that this tick's publish attempt failed.
+    pub fn record_error(&self) {
+        self.counters.errors.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A handle to a supervised background worker.
+///
+/// Owns the control channel used to pause/resume the worker, change its
+/// tick interval, or force an immediate out-of-band tick, plus a
+/// [`status`](Self::status) snapshot of its health. The task is tracked
+/// through its [`JoinHandle`] so a panic is visible in `status()` instead
+/// of silently vanishing, and dropping the handle cancels the task rather
+/// than leaving it running fire-and-forget.
+pub struct WorkerHandle {
+    command_tx: mpsc::UnboundedSender<WorkerCommand>,
+    counters: Arc<WorkerCounters>,
+    join_handle: JoinHandle<()>,
+}
+
+impl WorkerHandle {
+    /// Pauses ticking until [`resume`](Self::resume) is called.
+    ///
+    /// Also suppresses `extra` wakeups in [`WorkerControl::next_tick_or`],
+    /// so a caller whose loop is driven entirely by `next_tick_or` (rather
+    /// than the plain timer) is fully paused, not just its heartbeat.
+    pub fn pause(&self) {
+        let _ = self.command_tx.send(WorkerCommand::Pause);
+    }
+
+    /// Resumes ticking after [`pause`](Self::pause), including any `extra`
+    /// wakeup in [`WorkerControl::next_tick_or`] that became ready while
+    /// paused.
+    pub fn resume(&self) {
+        let _ = self.command_tx.send(WorkerCommand::Resume);
+    }
+
+    /// Changes the tick interval at runtime, effective from the next wait.
+    pub fn set_interval(&self, interval: Duration) {
+        let _ = self.command_tx.send(WorkerCommand::SetInterval(interval));
+    }
+
+    /// Requests a single out-of-band tick right now, even while paused.
+    pub fn republish(&self) {
+        let _ = self.command_tx.send(WorkerCommand::Republish);
+    }
+
+    /// Returns a snapshot of the worker's current state and counters.
+    pub fn status(&self) -> WorkerStatus {
+        let state = if self.join_handle.is_finished() {
+            WorkerState::Dead
+        } else if self.counters.idle.load(Ordering::Relaxed) {
+            WorkerState::Idle
+        } else {
+            WorkerState::Active
+        };
+
+        WorkerStatus {
+            state,
+            published: self.counters.published.load(Ordering::Relaxed),
+            last_publish_time_ms: match self.counters.last_publish_time_ms.load(Ordering::Relaxed) {
+                0 => None,
+                ms => Some(ms as u128),
+            },
+            errors: self.counters.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for WorkerHandle {
+    fn drop(&mut self) {
+        self.join_handle.abort();
+    }
+}
+
+/// Spawns `task` as a supervised worker ticking every `interval` until the
+/// returned [`WorkerHandle`] is dropped.
+///
+/// `task` is called once, with the [`WorkerControl`] it should loop on to
+/// wait for ticks and report back what each one did.
+pub fn spawn_worker<Fut>(
+    interval: Duration,
+    task: impl FnOnce(WorkerControl) -> Fut,
+) -> WorkerHandle
+where
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let (command_tx, command_rx) = mpsc::unbounded_channel();
+    let counters = Arc::new(WorkerCounters::default());
+
+    let control = WorkerControl {
+        command_rx,
+        counters: counters.clone(),
+        ticker: tokio::time::interval(interval),
+        paused: false,
+    };
+
+    let join_handle = tokio::task::spawn(task(control));
+
+    WorkerHandle {
+        command_tx,
+        counters,
+        join_handle,
+    }
+}